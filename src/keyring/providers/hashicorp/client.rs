@@ -1,5 +1,6 @@
 use abscissa_core::prelude::*;
 use std::collections::{BTreeMap, HashMap};
+use std::time::Instant;
 
 use super::error::Error;
 use hashicorp_vault::{
@@ -7,17 +8,67 @@ use hashicorp_vault::{
     Client,
 };
 
+use aes_kw::Kek;
+use ed25519_dalek::Verifier;
+use rand::RngCore;
+use rsa::{pkcs8::DecodePublicKey, Oaep, RsaPublicKey};
 use serde::{Deserialize, Serialize};
+use sha2::Sha256;
 
 const VAULT_BACKEND_NAME: &str = "transit";
 const PUBLIC_KEY_SIZE: usize = 32;
 const SIGNATURE_SIZE: usize = 64;
 pub const CONSENUS_KEY_TYPE: &str = "ed25519";
 
+/// Renew the token once this fraction of its lease has elapsed.
+const TOKEN_RENEWAL_THRESHOLD: f64 = 2.0 / 3.0;
+
+/// A non-renewable token is only treated as unusable once this fraction of
+/// its lease has elapsed, i.e. once it is genuinely about to expire. Below
+/// this, signing keeps working off the token it already has.
+const TOKEN_EXPIRY_THRESHOLD: f64 = 0.95;
+
 pub(crate) struct TendermintValidatorApp {
     client: Client<TokenData>,
+    host: String,
     key_name: String,
     public_key_value: Option<[u8; PUBLIC_KEY_SIZE]>,
+    token_state: TokenState,
+    verify_signatures: bool,
+}
+
+/// Tracks the lifecycle of the Vault token backing `client`, so it can be
+/// renewed before it expires instead of being treated as perpetual.
+#[derive(Debug)]
+struct TokenState {
+    issued_at: Instant,
+    ttl_seconds: u64,
+    renewable: bool,
+    /// `0` means Vault did not report an explicit cap.
+    explicit_max_ttl: u64,
+}
+
+impl TokenState {
+    fn should_renew(&self) -> bool {
+        if self.ttl_seconds == 0 {
+            return false;
+        }
+
+        let threshold = (self.ttl_seconds as f64 * TOKEN_RENEWAL_THRESHOLD) as u64;
+        self.issued_at.elapsed().as_secs() >= threshold
+    }
+
+    /// Whether the token is genuinely about to expire, as opposed to merely
+    /// past the renewal threshold. Used to decide whether a non-renewable
+    /// token should still be trusted to sign.
+    fn is_expiring_soon(&self) -> bool {
+        if self.ttl_seconds == 0 {
+            return false;
+        }
+
+        let threshold = (self.ttl_seconds as f64 * TOKEN_EXPIRY_THRESHOLD) as u64;
+        self.issued_at.elapsed().as_secs() >= threshold
+    }
 }
 
 // TODO(tarcieri): check this is actually sound?!
@@ -34,29 +85,279 @@ struct SignResponse {
     signature: String, //Base64 encoded
 }
 
+#[derive(Debug, Serialize)]
+struct BatchSignInput {
+    input: String, //Base64 encoded
+}
+
+#[derive(Debug, Serialize)]
+struct BatchSignRequest {
+    batch_input: Vec<BatchSignInput>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchSignResult {
+    signature: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchSignResponse {
+    batch_results: Vec<BatchSignResult>,
+}
+
+//signature: "vault:v1:/bcnnk4p8Uvidrs1/IX9s66UCOmmfdJudcV1/yek9a2deMiNGsVRSjirz6u+ti2wqUZfG6UukaoSHIDSSRV5Cw=="
+/// Parse a Vault transit `"vault:vN:<base64>"` signature into a fixed-size array.
+fn parse_vault_signature(raw: &str) -> Result<[u8; SIGNATURE_SIZE], Error> {
+    let parts = raw.split(":").collect::<Vec<&str>>();
+    if parts.len() != 3 {
+        return Err(Error::InvalidSignature(format!(
+            "expected 3 parts, received:{} full:{}",
+            parts.len(),
+            raw
+        )));
+    }
+
+    let base64_signature = if let Some(sign) = parts.last() {
+        sign.to_owned()
+    } else {
+        //this should never happen
+        return Err(Error::InvalidSignature("last part is not available".into()));
+    };
+
+    let signature = base64::decode(base64_signature)?;
+    if signature.len() != SIGNATURE_SIZE {
+        return Err(Error::InvalidSignature(format!(
+            "invalid signature length! {} == {}",
+            SIGNATURE_SIZE,
+            signature.len()
+        )));
+    }
+
+    let mut array = [0u8; SIGNATURE_SIZE];
+    array.copy_from_slice(&signature[..SIGNATURE_SIZE]);
+    Ok(array)
+}
+
 impl TendermintValidatorApp {
     pub fn connect(host: &str, token: &str, key_name: &str) -> Result<Self, Error> {
         //token self lookup
         let mut client = Client::new(host, token)?;
         client.secret_backend(VAULT_BACKEND_NAME);
 
+        let token_state = Self::lookup_token_state(&client)?;
+
         let app = TendermintValidatorApp {
             client,
+            host: host.to_owned(),
             key_name: key_name.to_owned(),
             public_key_value: None,
+            token_state,
+            verify_signatures: false,
         };
 
         debug!("Initialized with Vault host at {}", host);
         Ok(app)
     }
 
+    /// Opt into verifying every Vault-returned signature locally (against
+    /// the cached public key) before trusting it, as defense-in-depth
+    /// against a compromised or buggy Vault endpoint. Verification runs
+    /// in-process against the cached public key, so it does not add a
+    /// network round-trip to each signing call; it is still off by default.
+    pub fn with_signature_verification(mut self, enabled: bool) -> Self {
+        self.verify_signatures = enabled;
+        self
+    }
+
+    //vault write auth/approle/login role_id=<role_id> secret_id=<secret_id>
+    /// Authenticate via Vault's AppRole auth method, exchanging a role/secret
+    /// pair for a short-lived token, then connect as usual. This avoids
+    /// injecting a long-lived static token into TMKMS config.
+    pub fn connect_approle(
+        host: &str,
+        role_id: &str,
+        secret_id: &str,
+        key_name: &str,
+    ) -> Result<Self, Error> {
+        #[derive(Debug, Serialize)]
+        struct AppRoleLoginRequest {
+            role_id: String,
+            secret_id: String,
+        }
+
+        let body = AppRoleLoginRequest {
+            role_id: role_id.to_owned(),
+            secret_id: secret_id.to_owned(),
+        };
+
+        let token = Self::login(host, "auth/approle/login", &serde_json::to_string(&body)?)?;
+        Self::connect(host, &token, key_name)
+    }
+
+    //vault write auth/kubernetes/login role=<role> jwt=<service-account-jwt>
+    /// Authenticate via Vault's Kubernetes auth method, exchanging the pod's
+    /// service account JWT (read from `jwt_path`, typically the projected
+    /// token at `/var/run/secrets/kubernetes.io/serviceaccount/token`) for a
+    /// short-lived token, then connect as usual.
+    pub fn connect_kubernetes(
+        host: &str,
+        role: &str,
+        jwt_path: &str,
+        key_name: &str,
+    ) -> Result<Self, Error> {
+        #[derive(Debug, Serialize)]
+        struct KubernetesLoginRequest {
+            role: String,
+            jwt: String,
+        }
+
+        let jwt = std::fs::read_to_string(jwt_path).map_err(|err| {
+            Error::AuthError(format!(
+                "unable to read service account JWT at \"{}\":{}",
+                jwt_path, err
+            ))
+        })?;
+
+        let body = KubernetesLoginRequest {
+            role: role.to_owned(),
+            jwt: jwt.trim().to_owned(),
+        };
+
+        let token = Self::login(host, "auth/kubernetes/login", &serde_json::to_string(&body)?)?;
+        Self::connect(host, &token, key_name)
+    }
+
+    /// POST an auth method login request and return the issued client token.
+    fn login(host: &str, endpoint: &str, body: &str) -> Result<String, Error> {
+        let client = Client::new(host, "")?;
+
+        //the "data" field of these responses is always null; only "auth" is read
+        let data =
+            client.call_endpoint::<serde_json::Value>(HttpVerb::POST, endpoint, None, Some(body))?;
+
+        let auth = if let EndpointResponse::VaultResponse(VaultResponse {
+            auth: Some(auth), ..
+        }) = data
+        {
+            auth
+        } else {
+            return Err(Error::AuthError(format!(
+                "{}: no auth data returned",
+                endpoint
+            )));
+        };
+
+        Ok(auth.client_token)
+    }
+
+    //vault read auth/token/lookup-self
+    /// Fetch the TTL/renewability of the token backing `client`.
+    fn lookup_token_state(client: &Client<TokenData>) -> Result<TokenState, Error> {
+        let data =
+            client.call_endpoint::<TokenData>(HttpVerb::GET, "auth/token/lookup-self", None, None)?;
+
+        let data = if let EndpointResponse::VaultResponse(VaultResponse {
+            data: Some(data), ..
+        }) = data
+        {
+            data
+        } else {
+            return Err(Error::TokenRenewalFailed(
+                "lookup-self: no token data returned".into(),
+            ));
+        };
+
+        Ok(TokenState {
+            issued_at: Instant::now(),
+            ttl_seconds: data.ttl,
+            renewable: data.renewable,
+            explicit_max_ttl: data.explicit_max_ttl,
+        })
+    }
+
+    /// Renew the underlying Vault token if roughly two-thirds of its lease
+    /// has elapsed, refreshing the client's stored token in place. A
+    /// non-renewable token is left alone (with a warning) until it is
+    /// genuinely about to expire, rather than being rejected at the renewal
+    /// threshold — it is still perfectly valid for signing until then.
+    fn maybe_renew_token(&mut self) -> Result<(), Error> {
+        if !self.token_state.should_renew() {
+            return Ok(());
+        }
+
+        if !self.token_state.renewable {
+            if self.token_state.is_expiring_soon() {
+                return Err(Error::TokenNotRenewable);
+            }
+
+            warn!(
+                "vault token for {} is not renewable and is past its renewal threshold; \
+                 it will stop signing once it actually expires, ttl remaining:{}s",
+                self.key_name,
+                self.token_state
+                    .ttl_seconds
+                    .saturating_sub(self.token_state.issued_at.elapsed().as_secs())
+            );
+            return Ok(());
+        }
+
+        debug!("renewing vault token for {}...", self.key_name);
+
+        let requested_ttl = self.token_state.ttl_seconds;
+
+        //the "data" field of this response is always null; only "auth" is read
+        let data = self.client.call_endpoint::<serde_json::Value>(
+            HttpVerb::POST,
+            "auth/token/renew-self",
+            None,
+            None,
+        )?;
+
+        let auth = if let EndpointResponse::VaultResponse(VaultResponse {
+            auth: Some(auth), ..
+        }) = data
+        {
+            auth
+        } else {
+            return Err(Error::TokenRenewalFailed(
+                "renew-self: no auth data returned".into(),
+            ));
+        };
+
+        if auth.lease_duration == 0
+            || (self.token_state.explicit_max_ttl > 0 && auth.lease_duration < requested_ttl)
+        {
+            return Err(Error::TokenRenewalTruncated {
+                requested: requested_ttl,
+                granted: auth.lease_duration,
+            });
+        }
+
+        let mut client = Client::new(&self.host, &auth.client_token)?;
+        client.secret_backend(VAULT_BACKEND_NAME);
+        self.client = client;
+
+        self.token_state = TokenState {
+            issued_at: Instant::now(),
+            ttl_seconds: auth.lease_duration,
+            renewable: auth.renewable,
+            explicit_max_ttl: self.token_state.explicit_max_ttl,
+        };
+
+        debug!("vault token renewed, new ttl:{}s", auth.lease_duration);
+        Ok(())
+    }
+
     //vault read transit/keys/cosmoshub-sign-key
     //GET http://0.0.0.0:8200/v1/transit/keys/cosmoshub-sign-key
     /// Get public key
     pub fn public_key(&mut self) -> Result<[u8; PUBLIC_KEY_SIZE], Error> {
+        self.maybe_renew_token()?;
+
         if let Some(v) = self.public_key_value {
             debug!("using cached public key {}...", self.key_name);
-            return Ok(v.clone());
+            return Ok(v);
         }
 
         debug!("fetching public key for {}...", self.key_name);
@@ -127,11 +428,20 @@ impl TendermintValidatorApp {
             pubk.len()
         );
 
+        if pubk.len() != PUBLIC_KEY_SIZE {
+            return Err(Error::InvalidPubKey(format!(
+                "Public key \"{}\": invalid length! {} == {}",
+                self.key_name,
+                PUBLIC_KEY_SIZE,
+                pubk.len()
+            )));
+        }
+
         let mut array = [0u8; PUBLIC_KEY_SIZE];
         array.copy_from_slice(&pubk[..PUBLIC_KEY_SIZE]);
 
         //cache it...
-        self.public_key_value = Some(array.clone());
+        self.public_key_value = Some(array);
         debug!("Public key: value cached {}", self.key_name,);
 
         Ok(array)
@@ -140,12 +450,14 @@ impl TendermintValidatorApp {
     //vault write transit/sign/cosmoshub-sign-key plaintext=$(base64 <<< "some-data")
     //"https://127.0.0.1:8200/v1/transit/sign/cosmoshub-sign-key"
     /// Sign message
-    pub fn sign(&self, message: &[u8]) -> Result<[u8; SIGNATURE_SIZE], Error> {
+    pub fn sign(&mut self, message: &[u8]) -> Result<[u8; SIGNATURE_SIZE], Error> {
         debug!("signing request: received");
         if message.is_empty() {
             return Err(Error::InvalidEmptyMessage);
         }
 
+        self.maybe_renew_token()?;
+
         let body = SignRequest {
             input: base64::encode(message),
         };
@@ -170,38 +482,125 @@ impl TendermintValidatorApp {
             return Err(Error::NoSignature);
         };
 
-        let parts = data.signature.split(":").collect::<Vec<&str>>();
-        if parts.len() != 3 {
-            return Err(Error::InvalidSignature(format!(
-                "expected 3 parts, received:{} full:{}",
-                parts.len(),
-                data.signature
-            )));
+        let signature = parse_vault_signature(&data.signature)?;
+        self.verify_signature(message, &signature)?;
+        Ok(signature)
+    }
+
+    //vault write transit/sign/cosmoshub-sign-key plaintext=$(base64 <<< "a"),plaintext=$(base64 <<< "b")
+    /// Sign several messages in a single `transit/sign` request, preserving
+    /// message-to-signature ordering.
+    pub fn sign_batch(&mut self, messages: &[&[u8]]) -> Result<Vec<[u8; SIGNATURE_SIZE]>, Error> {
+        debug!("batch signing request: received {} message(s)", messages.len());
+        if messages.is_empty() {
+            return Err(Error::InvalidEmptyMessage);
         }
+        if messages.iter().any(|message| message.is_empty()) {
+            return Err(Error::InvalidEmptyMessage);
+        }
+
+        self.maybe_renew_token()?;
 
-        //signature: "vault:v1:/bcnnk4p8Uvidrs1/IX9s66UCOmmfdJudcV1/yek9a2deMiNGsVRSjirz6u+ti2wqUZfG6UukaoSHIDSSRV5Cw=="
-        let base64_signature = if let Some(sign) = parts.last() {
-            sign.to_owned()
+        let body = BatchSignRequest {
+            batch_input: messages
+                .iter()
+                .map(|message| BatchSignInput {
+                    input: base64::encode(message),
+                })
+                .collect(),
+        };
+
+        debug!("batch signing request: base64 encoded and about to submit for signing...");
+
+        let data = self.client.call_endpoint::<BatchSignResponse>(
+            HttpVerb::POST,
+            &format!("transit/sign/{}", self.key_name),
+            None,
+            Some(&serde_json::to_string(&body)?),
+        )?;
+
+        let data = if let EndpointResponse::VaultResponse(VaultResponse {
+            data: Some(data), ..
+        }) = data
+        {
+            data
         } else {
-            //this should never happen
-            return Err(Error::InvalidSignature("last part is not available".into()));
+            return Err(Error::NoSignature);
         };
 
-        let signature = base64::decode(base64_signature)?;
-        if signature.len() != 64 {
-            return Err(Error::InvalidSignature(format!(
-                "invalid signature length! 64 == {}",
-                signature.len()
-            )));
+        if data.batch_results.len() != messages.len() {
+            return Err(Error::BatchLengthMismatch {
+                sent: messages.len(),
+                received: data.batch_results.len(),
+            });
         }
 
-        let mut array = [0u8; SIGNATURE_SIZE];
-        array.copy_from_slice(&signature[..SIGNATURE_SIZE]);
-        Ok(array)
+        let signatures: Vec<[u8; SIGNATURE_SIZE]> = data
+            .batch_results
+            .into_iter()
+            .enumerate()
+            .map(|(index, result)| match result.signature {
+                Some(signature) => parse_vault_signature(&signature),
+                None => Err(Error::BatchElementError(
+                    index,
+                    result.error.unwrap_or_else(|| "no signature returned".into()),
+                )),
+            })
+            .collect::<Result<_, Error>>()?;
+
+        for (message, signature) in messages.iter().zip(signatures.iter()) {
+            self.verify_signature(message, signature)?;
+        }
+
+        Ok(signatures)
+    }
+
+    /// Verify a Vault-returned signature locally against the cached public
+    /// key, when `verify_signatures` is enabled. A no-op otherwise.
+    fn verify_signature(&mut self, message: &[u8], signature: &[u8]) -> Result<(), Error> {
+        if !self.verify_signatures {
+            return Ok(());
+        }
+
+        let public_key = self.public_key()?;
+        let signature: &[u8; SIGNATURE_SIZE] = signature.try_into().map_err(|_| {
+            Error::SignatureVerificationFailed("invalid ed25519 signature length".into())
+        })?;
+
+        let verifying_key = ed25519_dalek::VerifyingKey::from_bytes(&public_key)
+            .map_err(|err| Error::SignatureVerificationFailed(err.to_string()))?;
+        let signature = ed25519_dalek::Signature::from_bytes(signature);
+
+        let verified = verifying_key.verify(message, &signature).is_ok();
+
+        if verified {
+            Ok(())
+        } else {
+            Err(Error::SignatureVerificationFailed(format!(
+                "signature does not verify under the cached public key for \"{}\"",
+                self.key_name
+            )))
+        }
     }
 
     //The returned key will be a 4096-bit RSA public key.
+    /// Fetch Vault transit's RSA wrapping key, returning the second line of
+    /// its PEM encoding (the existing, public contract of this method).
     pub fn wrapping_key(&self) -> Result<String, Error> {
+        let pem = self.wrapping_key_pem()?;
+        if let Some(key) = pem.lines().nth(1) {
+            Ok(key.to_owned())
+        } else {
+            Err(Error::InvalidPubKey("Error getting wrapping key!".into()))
+        }
+    }
+
+    //The returned key will be a 4096-bit RSA public key.
+    /// Fetch Vault transit's RSA wrapping key as a complete PEM document.
+    /// `import_key` needs the full PEM to parse an `RsaPublicKey`, so this is
+    /// kept separate from the public `wrapping_key`, which only ever
+    /// returned one line of it.
+    fn wrapping_key_pem(&self) -> Result<String, Error> {
         #[derive(Debug, Deserialize)]
         struct PublicKeyResponse {
             public_key: String,
@@ -217,11 +616,7 @@ impl TendermintValidatorApp {
         Ok(
             if let EndpointResponse::VaultResponse(VaultResponse { data: Some(d), .. }) = data {
                 debug!("wrapping key:\n{}", d.public_key);
-                if let Some(key) = d.public_key.lines().nth(1) {
-                    key.to_owned()
-                } else {
-                    return Err(Error::InvalidPubKey("Error getting wrapping key!".into()));
-                }
+                d.public_key
             } else {
                 return Err(Error::InvalidPubKey("Error getting wrapping key!".into()));
             },
@@ -259,6 +654,54 @@ impl TendermintValidatorApp {
             },
         )
     }
+
+    //vault write transit/keys/<name>/import type=<key_type> ciphertext=<base64>
+    /// Securely import an externally generated key into Vault transit
+    /// using Vault's wrapped-import protocol: `raw_key` is wrapped under an
+    /// ephemeral AES-256 key (AES-KWP, RFC 5649), and that AES key is in
+    /// turn encrypted with RSA-OAEP (SHA-256/MGF1-SHA-256) under Vault's
+    /// wrapping key. This allows operators to bring their own key (e.g.
+    /// from an air-gapped ceremony or a softsign/YubiHSM backend) instead
+    /// of always letting Vault generate the key.
+    pub fn import_key(&self, key_name: &str, key_type: &str, raw_key: &[u8]) -> Result<(), Error> {
+        let wrapping_key = RsaPublicKey::from_public_key_pem(&self.wrapping_key_pem()?)
+            .map_err(|err| Error::RsaWrapError(err.to_string()))?;
+
+        let mut ephemeral_aes_key = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut ephemeral_aes_key);
+
+        let wrapped_target_key = Kek::from(ephemeral_aes_key)
+            .wrap_with_padding_vec(raw_key)
+            .map_err(|err| Error::AesWrapError(err.to_string()))?;
+
+        let wrapped_aes_key = wrapping_key
+            .encrypt(&mut rand::thread_rng(), Oaep::new::<Sha256>(), &ephemeral_aes_key)
+            .map_err(|err| Error::RsaWrapError(err.to_string()))?;
+
+        let mut ciphertext = wrapped_aes_key;
+        ciphertext.extend_from_slice(&wrapped_target_key);
+
+        #[derive(Debug, Serialize)]
+        struct ImportKeyRequest {
+            ciphertext: String,
+            r#type: String,
+        }
+
+        let body = ImportKeyRequest {
+            ciphertext: base64::encode(ciphertext),
+            r#type: key_type.to_owned(),
+        };
+
+        self.client.call_endpoint::<serde_json::Value>(
+            HttpVerb::POST,
+            &format!("transit/keys/{}/import", key_name),
+            None,
+            Some(&serde_json::to_string(&body)?),
+        )?;
+
+        debug!("imported key {} (type:{})", key_name, key_type);
+        Ok(())
+    }
 }
 
 // pub(super) enum ExportKeyTypeEnum {
@@ -280,14 +723,18 @@ mod tests {
     use super::*;
     use base64;
     use mockito::{mock, server_address};
+    use std::time::Duration;
 
     const TEST_TOKEN: &str = "test-token";
     const TEST_KEY_NAME: &str = "test-key-name";
-    const TEST_PUB_KEY_VALUE: &str = "ng+ab41LawVupIXX3ocMn+AfV2W1DEMCfjAdtrwXND8="; //base64
+    //genuine ed25519 keypair generated for these tests; TEST_SIGNATURE is a real signature of
+    //TEST_PAYLOAD_TO_SIGN under the private key matching TEST_PUB_KEY_VALUE, so verification
+    //(with_signature_verification) actually passes against it
+    const TEST_PUB_KEY_VALUE: &str = "/73T9TECUmoobC1QaWv9lvhWC32kKWrowIHjiRhYkFs="; //base64
     const TEST_PAYLOAD_TO_SIGN_BASE64: &str = "cXFxcXFxcXFxcXFxcXFxcXFxcXE="; //$(base64 <<< "qqqqqqqqqqqqqqqqqqqq") => "cXFxcXFxcXFxcXFxcXFxcXFxcXEK", 'K' vs "=" ????
     const TEST_PAYLOAD_TO_SIGN: &[u8] = b"qqqqqqqqqqqqqqqqqqqq";
 
-    const TEST_SIGNATURE:&str = /*vault:v1:*/ "pNcc/FAUu+Ta7itVegaMUMGqXYkzE777y3kOe8AtdRTgLbA8eFnrKbbX/m7zoiC+vArsIUJ1aMCEDRjDK3ZsBg==";
+    const TEST_SIGNATURE:&str = /*vault:v1:*/ "0Tn0JnvRlYdLo3AsO4le5l+vAk9zzLOYyevH0xrC8BVr+HLYXfJWIqY0dI/WbKxEfEXf4ckASgtNQlHSDKRmCQ==";
 
     #[test]
     fn hashicorp_connect_ok() {
@@ -307,6 +754,48 @@ mod tests {
         assert!(app.is_ok());
     }
 
+    #[test]
+    fn hashicorp_connect_approle_ok() {
+        //setup
+        const TEST_ROLE_ID: &str = "test-role-id";
+        const TEST_SECRET_ID: &str = "test-secret-id";
+
+        let _login = mock("POST", "/v1/auth/approle/login")
+            .with_body(format!(
+                r#"{{"request_id":"r","lease_id":"","renewable":true,"lease_duration":0,"data":null,"wrap_info":null,"warnings":null,"auth":{{"client_token":"{}","lease_duration":2758823,"renewable":true}}}}"#,
+                TEST_TOKEN
+            ))
+            .create();
+
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //test
+        let app = TendermintValidatorApp::connect_approle(
+            &format!("http://{}", server_address()),
+            TEST_ROLE_ID,
+            TEST_SECRET_ID,
+            TEST_KEY_NAME,
+        );
+
+        assert!(app.is_ok());
+    }
+
+    #[test]
+    fn hashicorp_connect_kubernetes_missing_jwt_should_fail() {
+        //test: no Vault interaction expected, the JWT file does not exist
+        let app = TendermintValidatorApp::connect_kubernetes(
+            &format!("http://{}", server_address()),
+            "test-role",
+            "/nonexistent/path/to/jwt",
+            TEST_KEY_NAME,
+        );
+
+        assert!(matches!(app, Err(Error::AuthError(_))));
+    }
+
     #[test]
     fn hashicorp_public_key_ok() {
         //setup
@@ -352,6 +841,40 @@ mod tests {
         read_key.assert();
     }
 
+    //curl --header "X-Vault-Token: $VAULT_TOKEN" "${VAULT_ADDR}/v1/transit/keys/<signing_key_name>", but for a
+    //key Vault reports as a different type than the app is configured for
+    const READ_KEY_RESP_WRONG_TYPE: &str = r#"
+    {"request_id":"9cb10d0a-1877-6da5-284b-8ece4b131ae3","lease_id":"","renewable":false,"lease_duration":0,"data":{"allow_plaintext_backup":false,"auto_rotate_period":0,"deletion_allowed":false,"derived":false,"exportable":false,"imported_key":false,"keys":{"1":{"creation_time":"2022-08-23T09:30:16.676998915Z","name":"ecdsa-p256","public_key":"/73T9TECUmoobC1QaWv9lvhWC32kKWrowIHjiRhYkFs="}},"latest_version":1,"min_available_version":0,"min_decryption_version":1,"min_encryption_version":0,"name":"cosmoshub-sign-key","supports_decryption":false,"supports_derivation":true,"supports_encryption":false,"supports_signing":true,"type":"ecdsa-p256"},"wrap_info":null,"warnings":null,"auth":null}
+    "#;
+
+    #[test]
+    fn hashicorp_public_key_wrong_key_type_should_fail() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app: configured for ed25519, but the key in Vault is reported as a different type
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        let _read_key = mock(
+            "GET",
+            format!("/v1/transit/keys/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(READ_KEY_RESP_WRONG_TYPE)
+        .create();
+
+        let res = app.public_key();
+        assert!(matches!(res, Err(Error::InvalidPubKey(_))));
+    }
+
     #[test]
     fn hashicorp_sign_ok() {
         //setup
@@ -361,7 +884,7 @@ mod tests {
             .create();
 
         //app
-        let app = TendermintValidatorApp::connect(
+        let mut app = TendermintValidatorApp::connect(
             &format!("http://{}", server_address()),
             TEST_TOKEN,
             TEST_KEY_NAME,
@@ -400,7 +923,7 @@ mod tests {
             .create();
 
         //app
-        let app = TendermintValidatorApp::connect(
+        let mut app = TendermintValidatorApp::connect(
             &format!("http://{}", server_address()),
             TEST_TOKEN,
             TEST_KEY_NAME,
@@ -426,6 +949,318 @@ mod tests {
         assert!(res.is_err());
     }
 
+    #[test]
+    fn hashicorp_sign_batch_ok() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        let body = serde_json::to_string(&BatchSignRequest {
+            batch_input: vec![
+                BatchSignInput {
+                    input: TEST_PAYLOAD_TO_SIGN_BASE64.into(),
+                },
+                BatchSignInput {
+                    input: TEST_PAYLOAD_TO_SIGN_BASE64.into(),
+                },
+            ],
+        })
+        .unwrap();
+
+        let _sign_mock = mock(
+            "POST",
+            format!("/v1/transit/sign/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .match_body(body.as_str())
+        .with_body(BATCH_SIGN_RESPONSE)
+        .create();
+
+        //server call
+        let res = app.sign_batch(&[TEST_PAYLOAD_TO_SIGN, TEST_PAYLOAD_TO_SIGN]);
+        let signatures = res.expect("batch sign failed");
+        assert_eq!(signatures.len(), 2);
+        for signature in signatures {
+            assert_eq!(
+                signature,
+                base64::decode(TEST_SIGNATURE).unwrap().as_slice()
+            );
+        }
+    }
+
+    #[test]
+    fn hashicorp_sign_batch_empty_should_fail() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        let res = app.sign_batch(&[]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn hashicorp_sign_batch_length_mismatch_should_fail() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        let _sign_mock = mock(
+            "POST",
+            format!("/v1/transit/sign/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(BATCH_SIGN_RESPONSE_SHORT) //only one result for two requested messages
+        .create();
+
+        //server call
+        let res = app.sign_batch(&[TEST_PAYLOAD_TO_SIGN, TEST_PAYLOAD_TO_SIGN]);
+        assert!(matches!(res, Err(Error::BatchLengthMismatch { .. })));
+    }
+
+    #[test]
+    fn hashicorp_sign_with_verification_ok() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app, with local signature verification opted in
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect")
+        .with_signature_verification(true);
+
+        let _read_key = mock(
+            "GET",
+            format!("/v1/transit/keys/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(READ_KEY_RESP)
+        .create();
+
+        let _sign_mock = mock(
+            "POST",
+            format!("/v1/transit/sign/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(SIGN_RESPONSE)
+        .create();
+
+        //server call
+        let res = app.sign(TEST_PAYLOAD_TO_SIGN);
+        assert_eq!(
+            res.expect("verified sign failed"),
+            base64::decode(TEST_SIGNATURE).unwrap()
+        );
+    }
+
+    #[test]
+    fn hashicorp_sign_with_verification_bad_signature_should_fail() {
+        //setup: SIGN_RESPONSE_WRONG does not verify against READ_KEY_RESP's public key
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect")
+        .with_signature_verification(true);
+
+        let _read_key = mock(
+            "GET",
+            format!("/v1/transit/keys/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(READ_KEY_RESP)
+        .create();
+
+        let _sign_mock = mock(
+            "POST",
+            format!("/v1/transit/sign/{}", TEST_KEY_NAME).as_str(),
+        )
+        .match_header("X-Vault-Token", TEST_TOKEN)
+        .with_body(SIGN_RESPONSE_WRONG)
+        .create();
+
+        //server call
+        let res = app.sign(TEST_PAYLOAD_TO_SIGN);
+        assert!(matches!(res, Err(Error::SignatureVerificationFailed(_))));
+    }
+
+    #[test]
+    fn hashicorp_token_renewal_not_renewable_should_fail() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        //pretend the lease is almost up and non-renewable
+        app.token_state = TokenState {
+            issued_at: Instant::now() - Duration::from_secs(100),
+            ttl_seconds: 90,
+            renewable: false,
+            explicit_max_ttl: 0,
+        };
+
+        let res = app.maybe_renew_token();
+        assert!(matches!(res, Err(Error::TokenNotRenewable)));
+    }
+
+    #[test]
+    fn hashicorp_token_renewal_not_renewable_but_not_expiring_should_continue() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        //past the 2/3 renewal threshold, but nowhere near actual expiry and non-renewable:
+        //should keep signing rather than erroring
+        app.token_state = TokenState {
+            issued_at: Instant::now() - Duration::from_secs(220),
+            ttl_seconds: 300,
+            renewable: false,
+            explicit_max_ttl: 0,
+        };
+
+        let res = app.maybe_renew_token();
+        assert!(res.is_ok());
+    }
+
+    #[test]
+    fn hashicorp_token_renewal_ok() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        //pretend the lease is almost up
+        app.token_state = TokenState {
+            issued_at: Instant::now() - Duration::from_secs(100),
+            ttl_seconds: 90,
+            renewable: true,
+            explicit_max_ttl: 0,
+        };
+
+        const NEW_TOKEN: &str = "renewed-test-token";
+        let _renew_mock = mock("POST", "/v1/auth/token/renew-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(format!(
+                r#"{{"request_id":"r","lease_id":"","renewable":true,"lease_duration":0,"data":null,"wrap_info":null,"warnings":null,"auth":{{"client_token":"{}","lease_duration":2758823,"renewable":true}}}}"#,
+                NEW_TOKEN
+            ))
+            .create();
+
+        //the client rebuilt around the renewed token looks itself up again
+        let _lookup_renewed = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", NEW_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        let res = app.maybe_renew_token();
+        assert!(res.is_ok());
+        assert_eq!(app.token_state.ttl_seconds, 2758823);
+    }
+
+    #[test]
+    fn hashicorp_token_renewal_truncated_should_fail() {
+        //setup
+        let _lookup_self = mock("GET", "/v1/auth/token/lookup-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(TOKEN_DATA)
+            .create();
+
+        //app
+        let mut app = TendermintValidatorApp::connect(
+            &format!("http://{}", server_address()),
+            TEST_TOKEN,
+            TEST_KEY_NAME,
+        )
+        .expect("Failed to connect");
+
+        //pretend the lease is almost up and Vault enforces a lower explicit_max_ttl
+        app.token_state = TokenState {
+            issued_at: Instant::now() - Duration::from_secs(100),
+            ttl_seconds: 90,
+            renewable: true,
+            explicit_max_ttl: 45,
+        };
+
+        let _renew_mock = mock("POST", "/v1/auth/token/renew-self")
+            .match_header("X-Vault-Token", TEST_TOKEN)
+            .with_body(
+                r#"{"request_id":"r","lease_id":"","renewable":true,"lease_duration":0,"data":null,"wrap_info":null,"warnings":null,"auth":{"client_token":"test-token","lease_duration":45,"renewable":true}}"#,
+            )
+            .create();
+
+        let res = app.maybe_renew_token();
+        assert!(matches!(res, Err(Error::TokenRenewalTruncated { .. })));
+    }
+
     //curl --header "X-Vault-Token: hvs.<...valid.token...>>" http://127.0.0.1:8200/v1/auth/token/lookup-self
     const TOKEN_DATA: &str = r#"
     {"request_id":"119fcc9e-85e2-1fcf-c2a2-96cfb20f7446","lease_id":"","renewable":false,"lease_duration":0,"data":{"accessor":"k1g6PqNWVIlKK9NDCWLiTvrG","creation_time":1661247016,"creation_ttl":2764800,"display_name":"token","entity_id":"","expire_time":"2022-09-24T09:30:16.898359776Z","explicit_max_ttl":0,"id":"hvs.CAESIEzWRWLvyYLGlYsCRI_Vt653K26b-cx_lrxBlFo3_2GBGh4KHGh2cy5GVzZ5b25nMVFpSkwzM1B1eHM2Y0ZqbXA","issue_time":"2022-08-23T09:30:16.898363509Z","meta":null,"num_uses":0,"orphan":false,"path":"auth/token/create","policies":["tmkms-transit-sign-policy"],"renewable":false,"ttl":2758823,"type":"service"},"wrap_info":null,"warnings":null,"auth":null}
@@ -433,11 +1268,25 @@ mod tests {
 
     //curl --header "X-Vault-Token: $VAULT_TOKEN" "${VAULT_ADDR}/v1/transit/keys/<signing_key_name>"
     const READ_KEY_RESP: &str = r#"
-    {"request_id":"9cb10d0a-1877-6da5-284b-8ece4b131ae3","lease_id":"","renewable":false,"lease_duration":0,"data":{"allow_plaintext_backup":false,"auto_rotate_period":0,"deletion_allowed":false,"derived":false,"exportable":false,"imported_key":false,"keys":{"1":{"creation_time":"2022-08-23T09:30:16.676998915Z","name":"ed25519","public_key":"ng+ab41LawVupIXX3ocMn+AfV2W1DEMCfjAdtrwXND8="}},"latest_version":1,"min_available_version":0,"min_decryption_version":1,"min_encryption_version":0,"name":"cosmoshub-sign-key","supports_decryption":false,"supports_derivation":true,"supports_encryption":false,"supports_signing":true,"type":"ed25519"},"wrap_info":null,"warnings":null,"auth":null}    
+    {"request_id":"9cb10d0a-1877-6da5-284b-8ece4b131ae3","lease_id":"","renewable":false,"lease_duration":0,"data":{"allow_plaintext_backup":false,"auto_rotate_period":0,"deletion_allowed":false,"derived":false,"exportable":false,"imported_key":false,"keys":{"1":{"creation_time":"2022-08-23T09:30:16.676998915Z","name":"ed25519","public_key":"/73T9TECUmoobC1QaWv9lvhWC32kKWrowIHjiRhYkFs="}},"latest_version":1,"min_available_version":0,"min_decryption_version":1,"min_encryption_version":0,"name":"cosmoshub-sign-key","supports_decryption":false,"supports_derivation":true,"supports_encryption":false,"supports_signing":true,"type":"ed25519"},"wrap_info":null,"warnings":null,"auth":null}    
     "#;
 
     //curl --request POST --header "X-Vault-Token: $VAULT_TOKEN" "${VAULT_ADDR}/v1/transit/sign/<..key_name...>" -d '{"input":"base64 encoded"}'
     const SIGN_RESPONSE: &str = r#"
-    {"request_id":"13534911-8e98-9a0f-a701-e9a7736140e2","lease_id":"","renewable":false,"lease_duration":0,"data":{"key_version":1,"signature":"vault:v1:pNcc/FAUu+Ta7itVegaMUMGqXYkzE777y3kOe8AtdRTgLbA8eFnrKbbX/m7zoiC+vArsIUJ1aMCEDRjDK3ZsBg=="},"wrap_info":null,"warnings":null,"auth":null}
+    {"request_id":"13534911-8e98-9a0f-a701-e9a7736140e2","lease_id":"","renewable":false,"lease_duration":0,"data":{"key_version":1,"signature":"vault:v1:0Tn0JnvRlYdLo3AsO4le5l+vAk9zzLOYyevH0xrC8BVr+HLYXfJWIqY0dI/WbKxEfEXf4ckASgtNQlHSDKRmCQ=="},"wrap_info":null,"warnings":null,"auth":null}
+    "#;
+
+    //same shape as SIGN_RESPONSE but with an unrelated (still 64-byte) signature, so it does not verify
+    const SIGN_RESPONSE_WRONG: &str = r#"
+    {"request_id":"13534911-8e98-9a0f-a701-e9a7736140e2","lease_id":"","renewable":false,"lease_duration":0,"data":{"key_version":1,"signature":"vault:v1:Ljq2V1XSaxNcSVCZz4Pz2VHQUaRcF6vbFNbzMYCEvlq8wBUUN2OHoYhBWH59h3bJx7lC1bI5GU0R+xw0PilUAg=="},"wrap_info":null,"warnings":null,"auth":null}
+    "#;
+
+    //curl --request POST --header "X-Vault-Token: $VAULT_TOKEN" "${VAULT_ADDR}/v1/transit/sign/<..key_name...>" -d '{"batch_input":[{"input":"base64 encoded"},{"input":"base64 encoded"}]}'
+    const BATCH_SIGN_RESPONSE: &str = r#"
+    {"request_id":"13534911-8e98-9a0f-a701-e9a7736140e2","lease_id":"","renewable":false,"lease_duration":0,"data":{"batch_results":[{"signature":"vault:v1:0Tn0JnvRlYdLo3AsO4le5l+vAk9zzLOYyevH0xrC8BVr+HLYXfJWIqY0dI/WbKxEfEXf4ckASgtNQlHSDKRmCQ=="},{"signature":"vault:v1:0Tn0JnvRlYdLo3AsO4le5l+vAk9zzLOYyevH0xrC8BVr+HLYXfJWIqY0dI/WbKxEfEXf4ckASgtNQlHSDKRmCQ=="}]},"wrap_info":null,"warnings":null,"auth":null}
+    "#;
+
+    const BATCH_SIGN_RESPONSE_SHORT: &str = r#"
+    {"request_id":"13534911-8e98-9a0f-a701-e9a7736140e2","lease_id":"","renewable":false,"lease_duration":0,"data":{"batch_results":[{"signature":"vault:v1:0Tn0JnvRlYdLo3AsO4le5l+vAk9zzLOYyevH0xrC8BVr+HLYXfJWIqY0dI/WbKxEfEXf4ckASgtNQlHSDKRmCQ=="}]},"wrap_info":null,"warnings":null,"auth":null}
     "#;
 }
\ No newline at end of file
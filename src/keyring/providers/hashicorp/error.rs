@@ -22,8 +22,8 @@ pub enum Error {
     #[error("received no signature back")]
     NoSignature,
 
-    #[error("received an invalid signature")]
-    InvalidSignature,
+    #[error("received an invalid signature:{0}")]
+    InvalidSignature(String),
 
     #[error("ApiClient error")]
     ApiClientError(hashicorp_vault::Error),
@@ -33,6 +33,35 @@ pub enum Error {
 
     #[error("SerDe error")]
     SerDeError(serde_json::Error),
+
+    #[error("vault token is not renewable, rotate credentials")]
+    TokenNotRenewable,
+
+    #[error("vault token renewal failed:{0}")]
+    TokenRenewalFailed(String),
+
+    #[error(
+        "vault token renewal granted a shorter lease than requested (requested:{requested}s granted:{granted}s), rotate credentials"
+    )]
+    TokenRenewalTruncated { requested: u64, granted: u64 },
+
+    #[error("batch sign: result count mismatch, sent:{sent} received:{received}")]
+    BatchLengthMismatch { sent: usize, received: usize },
+
+    #[error("batch sign: element {0} returned no signature:{1}")]
+    BatchElementError(usize, String),
+
+    #[error("RSA wrapping error:{0}")]
+    RsaWrapError(String),
+
+    #[error("AES-KWP wrapping error:{0}")]
+    AesWrapError(String),
+
+    #[error("Vault auth method login failed:{0}")]
+    AuthError(String),
+
+    #[error("signature verification failed:{0}")]
+    SignatureVerificationFailed(String),
 }
 
 impl From<hashicorp_vault::Error> for Error {